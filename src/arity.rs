@@ -0,0 +1,20 @@
+/// How many times an argument may be supplied, as xflags models it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Arity {
+    /// May be supplied at most once, or omitted.
+    Optional,
+    /// Must be supplied exactly once.
+    Required,
+    /// May be supplied any number of times; all values are collected.
+    Repeated,
+}
+
+impl Arity {
+    pub fn is_required(&self) -> bool {
+        matches!(self, Arity::Required)
+    }
+
+    pub fn is_repeated(&self) -> bool {
+        matches!(self, Arity::Repeated)
+    }
+}
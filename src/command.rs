@@ -0,0 +1,58 @@
+use crate::ArgumentInfo;
+
+/// Name used for the top-level command when no subcommand was selected.
+pub const ANONYMOUS_COMMAND_NAME: &str = "anonymous";
+
+/// A named set of arguments, optionally owning further nested subcommands.
+///
+/// Mirrors the way `xflags` models a CLI: each `Command` carries its own
+/// [`ArgumentInfo`] list, and the parser picks one `Command` (by name) out of a tree
+/// before validating the remaining tokens against it.
+pub struct Command<'a> {
+    name: &'a str,
+    args: Vec<ArgumentInfo<'a>>,
+    subcommands: Vec<Command<'a>>,
+}
+
+impl<'a> Command<'a> {
+    /// Creates a new, argument-less command with the given `name`.
+    pub fn new(name: &'a str) -> Self {
+        Command {
+            name,
+            args: Vec::new(),
+            subcommands: Vec::new(),
+        }
+    }
+
+    /// Adds an argument to this command's flag set.
+    pub fn arg(mut self, arg: ArgumentInfo<'a>) -> Self {
+        self.args.push(arg);
+        self
+    }
+
+    /// Adds a nested subcommand.
+    // No callers in the `firecracker` binary yet: `build_command` doesn't register any
+    // subcommands. Exercised by `resolve_command`'s tests in `main.rs`, which is why
+    // this isn't dead in practice, but that doesn't save it from the lint on a
+    // non-test build.
+    #[allow(dead_code)]
+    pub fn subcommand(mut self, command: Command<'a>) -> Self {
+        self.subcommands.push(command);
+        self
+    }
+
+    /// Returns this command's name.
+    pub fn name(&self) -> &'a str {
+        self.name
+    }
+
+    /// Returns this command's own arguments.
+    pub fn args(&self) -> &[ArgumentInfo<'a>] {
+        &self.args
+    }
+
+    /// Looks up a direct subcommand by name.
+    pub fn find_subcommand(&self, name: &str) -> Option<&Command<'a>> {
+        self.subcommands.iter().find(|c| c.name == name)
+    }
+}
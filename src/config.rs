@@ -0,0 +1,35 @@
+use std::collections::HashMap;
+use std::fs;
+
+use serde_json::Value;
+
+use crate::error::Error;
+
+/// Reads `path` as a JSON object mapping argument names to values, returning a flat
+/// name-to-string map that can be merged with the values CLI parsing produces. Lets operators supply
+/// configuration via file or flags interchangeably, the way virtiofsd/Firecracker do.
+pub fn load(path: &str) -> Result<HashMap<String, String>, Error> {
+    let contents = fs::read_to_string(path).map_err(|_| Error::ConfigFileNotFound(path.to_string()))?;
+    let parsed: Value =
+        serde_json::from_str(&contents).map_err(|e| Error::InvalidConfigFile(e.to_string()))?;
+    let object = parsed
+        .as_object()
+        .ok_or_else(|| Error::InvalidConfigFile("expected a JSON object".to_string()))?;
+
+    let mut values = HashMap::new();
+    for (name, value) in object {
+        let value = match value {
+            Value::String(s) => s.clone(),
+            Value::Bool(b) => b.to_string(),
+            Value::Number(n) => n.to_string(),
+            _ => {
+                return Err(Error::InvalidConfigFile(format!(
+                    "value for '{}' must be a string, number or boolean",
+                    name
+                )))
+            }
+        };
+        values.insert(name.clone(), value);
+    }
+    Ok(values)
+}
@@ -0,0 +1,67 @@
+use std::fmt;
+
+/// Errors that can occur while defining or parsing command-line arguments.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Error {
+    /// An argument was found that wasn't declared in the parser.
+    UnknownArgument(String),
+    /// An argument that takes a value was passed without one.
+    MissingRequiredValue(String),
+    /// A required argument was not found among the supplied arguments.
+    MissingRequiredArgument(String),
+    /// Two arguments that cannot be used together were both found.
+    Conflict(String, String),
+    /// A value was found for an argument that doesn't take one.
+    UnexpectedValue(String, String),
+    /// The value supplied for an argument failed validation.
+    InvalidValue(String, String),
+    /// A non-repeatable argument was supplied more than once.
+    DuplicateArgument(String),
+    /// The config file passed via `--config-file` doesn't exist or couldn't be read.
+    ConfigFileNotFound(String),
+    /// The config file passed via `--config-file` isn't valid JSON, or doesn't match
+    /// the shape expected (an object of argument-name to value).
+    InvalidConfigFile(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::UnknownArgument(arg) => write!(
+                f,
+                "Found argument '{}' which wasn't expected, or isn't valid in this context.",
+                arg
+            ),
+            Error::MissingRequiredValue(arg) => {
+                write!(f, "The argument '{}' requires a value, but none was supplied.", arg)
+            }
+            Error::MissingRequiredArgument(arg) => {
+                write!(f, "Argument '{}' required, but not found.", arg)
+            }
+            Error::Conflict(arg, other) => write!(
+                f,
+                "Argument '{}' cannot be used with argument '{}'.",
+                arg, other
+            ),
+            Error::UnexpectedValue(arg, value) => write!(
+                f,
+                "Argument '{}' does not take a value, but '{}' was supplied.",
+                arg, value
+            ),
+            Error::InvalidValue(arg, value) => {
+                write!(f, "'{}' isn't a valid value for '{}'.", value, arg)
+            }
+            Error::DuplicateArgument(arg) => {
+                write!(f, "The argument '{}' was supplied more than once.", arg)
+            }
+            Error::ConfigFileNotFound(path) => {
+                write!(f, "Could not find or read the config file at '{}'.", path)
+            }
+            Error::InvalidConfigFile(reason) => {
+                write!(f, "Could not parse the config file: {}.", reason)
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {}
@@ -0,0 +1,84 @@
+use crate::command::{Command, ANONYMOUS_COMMAND_NAME};
+use crate::ArgumentInfo;
+
+/// Renders the full `--help` output for `command`: a one-line description, a `USAGE:`
+/// synopsis, and the argument list grouped under each [`ArgumentInfo`]'s `heading`.
+pub fn render_help(command: &Command, program: &str, description: &str) -> String {
+    let mut out = String::new();
+    out.push_str(description);
+    out.push_str("\n\n");
+    out.push_str(&render_usage(command, program));
+    out.push_str("\n\n");
+    out.push_str(&render_arguments(command));
+    out
+}
+
+fn render_usage(command: &Command, program: &str) -> String {
+    let mut line = format!("USAGE:\n    {}", program);
+    if command.name() != ANONYMOUS_COMMAND_NAME {
+        line.push(' ');
+        line.push_str(command.name());
+    }
+    for arg in command.args() {
+        line.push(' ');
+        let body = flag_column(arg);
+        if arg.is_required() {
+            line.push_str(&body);
+        } else {
+            line.push('[');
+            line.push_str(&body);
+            line.push(']');
+        }
+    }
+    line
+}
+
+fn render_arguments(command: &Command) -> String {
+    let mut headings: Vec<Option<&str>> = Vec::new();
+    for arg in command.args() {
+        if !headings.contains(&arg.heading_name()) {
+            headings.push(arg.heading_name());
+        }
+    }
+
+    let flag_column_width = command
+        .args()
+        .iter()
+        .map(|arg| flag_column(arg).len())
+        .max()
+        .unwrap_or(0);
+
+    let mut out = String::new();
+    for (i, heading) in headings.iter().enumerate() {
+        if i > 0 {
+            out.push('\n');
+        }
+        out.push_str(heading.unwrap_or("OPTIONS"));
+        out.push_str(":\n");
+        for arg in command.args().iter().filter(|arg| arg.heading_name() == *heading) {
+            out.push_str(&format!(
+                "    {:<width$}  {}\n",
+                flag_column(arg),
+                describe(arg),
+                width = flag_column_width
+            ));
+        }
+    }
+    out
+}
+
+fn flag_column(arg: &ArgumentInfo) -> String {
+    match (arg.short_flag(), arg.wants_value()) {
+        (Some(short), true) => format!("-{}, --{} <{}>", short, arg.name(), arg.name()),
+        (Some(short), false) => format!("-{}, --{}", short, arg.name()),
+        (None, true) => format!("--{} <{}>", arg.name(), arg.name()),
+        (None, false) => format!("--{}", arg.name()),
+    }
+}
+
+fn describe(arg: &ArgumentInfo) -> String {
+    match arg.default_value() {
+        Some(default) => format!("{} [default: {}]", arg.help_text(), default),
+        None => arg.help_text().to_string(),
+    }
+}
@@ -1,238 +1,731 @@
+mod arity;
+mod command;
+mod config;
+mod error;
+mod help;
+mod value_parser;
+
 use std::collections::HashMap;
 use std::env;
 use std::path::PathBuf;
+use std::process;
+
+use arity::Arity;
+use command::{Command, ANONYMOUS_COMMAND_NAME};
+use error::Error;
+use value_parser::ValueParser;
 
 const DEFAULT_API_SOCK_PATH: &str = "/tmp/firecracker.socket";
 const DEFAULT_INSTANCE_ID: &str = "anonymous-instance";
+const PROGRAM_NAME: &str = "firecracker";
+const PROGRAM_DESCRIPTION: &str = "Stuff about firecracker";
 
-struct ArgumentInfo<'a> {
+pub struct ArgumentInfo<'a> {
     name: &'a str,
-    required: bool,
+    arity: Arity,
     conflicts_with: Option<&'a str>,
     requires: Option<&'a str>,
     takes_value: bool,
     default_value: Option<&'a str>,
     help: &'a str,
+    value_parser: Option<ValueParser<'a>>,
+    short: Option<char>,
+    heading: Option<&'a str>,
 }
 
 impl<'a> ArgumentInfo<'a> {
-    pub fn new(
-        name: &'a str,
-        required: bool,
-        conflicts_with: Option<&'a str>,
-        requires: Option<&'a str>,
-        takes_value: bool,
-        default_value: Option<&'a str>,
-        help: &'a str,
-    ) -> ArgumentInfo<'a> {
+    /// Starts building an argument named `name`, with every other property left at its
+    /// default (optional, no value, no help text). Chain the setters below to fill in
+    /// the rest; this replaces a long positional constructor that made it easy to swap
+    /// e.g. `conflicts_with` and `requires` by accident.
+    pub fn named(name: &'a str) -> Self {
         ArgumentInfo {
             name,
-            required,
-            conflicts_with,
-            requires,
-            takes_value,
-            default_value,
-            help,
+            arity: Arity::Optional,
+            conflicts_with: None,
+            requires: None,
+            takes_value: false,
+            default_value: None,
+            help: "",
+            value_parser: None,
+            short: None,
+            heading: None,
         }
     }
-    pub fn display_help(&self) {
-        println!("{}: {}", self.name, self.help);
+
+    /// Sets whether this argument must be supplied exactly once.
+    pub fn required(mut self, required: bool) -> Self {
+        self.arity = if required {
+            Arity::Required
+        } else {
+            Arity::Optional
+        };
+        self
+    }
+
+    /// Marks this argument as repeatable: it may be supplied any number of times, and
+    /// all of its values are collected.
+    pub fn repeated(mut self) -> Self {
+        self.arity = Arity::Repeated;
+        self
+    }
+
+    /// Marks this argument as taking a value, e.g. `--api-sock <PATH>` rather than a
+    /// bare flag like `--no-api`.
+    pub fn takes_value(mut self) -> Self {
+        self.takes_value = true;
+        self
+    }
+
+    /// Sets the value used when this argument isn't supplied on the command line or in
+    /// a config file.
+    pub fn default(mut self, default_value: &'a str) -> Self {
+        self.default_value = Some(default_value);
+        self
+    }
+
+    /// Sets the help text shown for this argument in `--help` output.
+    pub fn help(mut self, help: &'a str) -> Self {
+        self.help = help;
+        self
+    }
+
+    /// Validates values supplied for this argument against `value_parser`.
+    pub fn value_parser(mut self, value_parser: ValueParser<'a>) -> Self {
+        self.value_parser = Some(value_parser);
+        self
+    }
+
+    /// Registers a single-character alias, e.g. `-i` for `--id`.
+    pub fn short(mut self, short: char) -> Self {
+        self.short = Some(short);
+        self
+    }
+
+    /// Groups this argument under `heading` in `--help` output.
+    pub fn heading(mut self, heading: &'a str) -> Self {
+        self.heading = Some(heading);
+        self
+    }
+
+    /// Declares that this argument cannot be used together with `name`.
+    pub fn conflicts_with(mut self, name: &'a str) -> Self {
+        self.conflicts_with = Some(name);
+        self
+    }
+
+    /// Declares that this argument can only be used together with `name`.
+    pub fn requires(mut self, name: &'a str) -> Self {
+        self.requires = Some(name);
+        self
+    }
+
+    pub(crate) fn name(&self) -> &'a str {
+        self.name
+    }
+
+    pub(crate) fn is_required(&self) -> bool {
+        self.arity.is_required()
+    }
+
+    pub(crate) fn wants_value(&self) -> bool {
+        self.takes_value
+    }
+
+    pub(crate) fn default_value(&self) -> Option<&'a str> {
+        self.default_value
+    }
+
+    pub(crate) fn help_text(&self) -> &'a str {
+        self.help
+    }
+
+    pub(crate) fn short_flag(&self) -> Option<char> {
+        self.short
+    }
+
+    pub(crate) fn heading_name(&self) -> Option<&'a str> {
+        self.heading
     }
 }
 
-fn check_is_valid(argument_info: &ArgumentInfo, params: &Vec<&str>) {
+/// Argument values keyed by name. Every value is stored as a `Vec` so arguments declared
+/// [`Arity::Repeated`] can collect all of their occurrences.
+type ParsedValues = HashMap<String, Vec<String>>;
+
+/// Parsed arguments, keyed by argument name.
+pub struct Arguments {
+    /// The command path that was selected, e.g. `["anonymous"]` or `["anonymous", "run"]`.
+    command_path: Vec<String>,
+    values: ParsedValues,
+    /// Tokens found after a `--` separator, passed through verbatim.
+    extra_args: Vec<String>,
+}
+
+impl Arguments {
+    /// Returns the first value associated with `name`, if the argument was supplied or
+    /// has a default value.
+    pub fn value_of(&self, name: &str) -> Option<&str> {
+        self.values.get(name).and_then(|v| v.first()).map(|s| s.as_str())
+    }
+
+    /// Returns every value collected for `name`, in the order they were supplied. Only
+    /// ever has more than one element for arguments declared [`Arity::Repeated`].
+    pub fn values_of(&self, name: &str) -> Option<&[String]> {
+        self.values.get(name).map(|v| v.as_slice())
+    }
+
+    /// Returns the sequence of command names that were resolved while parsing, starting
+    /// with the top-level command.
+    pub fn command_path(&self) -> &[String] {
+        &self.command_path
+    }
+
+    /// Returns the tokens found after a `--` separator, in the order they were supplied.
+    pub fn extra_args(&self) -> &[String] {
+        &self.extra_args
+    }
+}
+
+/// What parsing a token stream produced. `--help` is reported as [`ParseOutcome::Help`]
+/// rather than printed and exited on the spot, so library callers (not just `main`) get
+/// to decide how to show it.
+pub enum ParseOutcome {
+    /// The tokens parsed successfully into [`Arguments`].
+    Parsed(Arguments),
+    /// `--help` (or `-h`) was supplied; this is the rendered help text.
+    Help(String),
+}
+
+fn check_is_valid(argument_info: &ArgumentInfo, params: &[&str]) -> Result<(), Error> {
     if let Some(arg_name) = argument_info.conflicts_with {
         if params.contains(&arg_name) {
-            panic!(
-                "Found argument '{}' which wasn't expected, or isn't valid in this context.",
-                arg_name
-            );
+            return Err(Error::Conflict(argument_info.name.to_string(), arg_name.to_string()));
         }
     }
     if let Some(arg_name) = argument_info.requires {
         if !params.contains(&arg_name) {
-            panic!("Argument '{}' required, but not found.", arg_name);
+            return Err(Error::MissingRequiredArgument(arg_name.to_string()));
         }
     }
-    if argument_info.takes_value {
-        if argument_info.required && argument_info.default_value.is_none() {
-            if !params.contains(&argument_info.name) {
-                panic!("Argument '{}' required, but not found.", argument_info.name);
-            }
+    Ok(())
+}
+
+/// Checks that every [`Arity::Required`] argument in `command` without a default was
+/// actually supplied. Runs against `values` *after* [`merge_values`] has layered in the
+/// config file, since a required argument supplied only via `--config-file` must count
+/// as present, matching the CLI > config > default precedence the rest of this parser
+/// follows.
+fn check_required(
+    command: &Command<'_>,
+    values: &ParsedValues,
+) -> Result<(), Error> {
+    for argument_info in command.args() {
+        if argument_info.arity.is_required()
+            && argument_info.default_value.is_none()
+            && !values.contains_key(argument_info.name)
+        {
+            return Err(Error::MissingRequiredArgument(argument_info.name.to_string()));
         }
     }
+    Ok(())
 }
 
-
-fn main() {
-    let args: Vec<String> = env::args().collect();
-    let args: Vec<&str> = args.iter().map(|s| s as &str).collect();
-
-    let params: Vec<&str> = args
+/// Resolves a short flag character to the long-form argument name it aliases, and
+/// whether that argument takes a value. `-h` is always accepted as an alias for
+/// `--help`, even though `help` isn't a declared [`ArgumentInfo`].
+fn resolve_short<'a>(command: &Command<'a>, short: char) -> Result<(&'a str, bool), Error> {
+    if short == 'h' {
+        return Ok(("help", false));
+    }
+    command
+        .args()
         .iter()
-        .filter(|x| x.starts_with("--"))
-        .map(|&x| x.trim_start_matches("--"))
-        .collect();
+        .find(|arg| arg.short == Some(short))
+        .map(|arg| (arg.name, arg.takes_value))
+        .ok_or_else(|| Error::UnknownArgument(format!("-{}", short)))
+}
+
+/// Returns whether `name` is a declared argument that takes a value (`help` never does).
+fn arg_takes_value(command: &Command<'_>, name: &str) -> bool {
+    name != "help" && command.args().iter().any(|arg| arg.name == name && arg.takes_value)
+}
 
-    let fc_extra_args: Vec<&str> = if params.last().unwrap().is_empty() {
-        let index = args.iter().position(|&r| r == "--").unwrap();
-        let (_, extra_args) = args.split_at(index + 1);
-        extra_args.to_vec()
-    } else {
-        Vec::new()
+/// Returns whether `--help` was requested, ignoring anything past a `--` separator
+/// (those tokens are passthrough, not flags for this parser).
+fn wants_help(normalized: &[String]) -> bool {
+    let command_args = match normalized.iter().position(|x| x == "--") {
+        Some(index) => &normalized[..index],
+        None => normalized,
     };
+    command_args.iter().any(|x| x == "--help")
+}
 
-    for extra_arg in fc_extra_args {
-        println!("{}", extra_arg);
-    }
-
-    let mut fc_args = Vec::new();
-    let fc_args_str = [
-        "api-sock",
-        "id",
-        "seccomp-level",
-        "start-time-us",
-        "start-time-cpu-us",
-        "no-api",
-        "config-file",
-    ];
-    let seccomp_values = ["0", "1", "2"];
-    let mut values = HashMap::new();
-
-    fc_args.push(ArgumentInfo::new(
-        "api-sock",
-        false,
-        None,
-        None,
-        true,
-        Some(DEFAULT_API_SOCK_PATH),
-        "Path to unix domain socket used by the API",
-    ));
-    fc_args.push(ArgumentInfo::new(
-        "id",
-        false,
-        None,
-        None,
-        true,
-        Some(DEFAULT_INSTANCE_ID),
-        "MicroVM unique identifier",
-    ));
-    fc_args.push(ArgumentInfo::new(
-        "seccomp-level",
-        false,
-        None,
-        None,
-        true,
-        Some("2"),
-        "Level of seccomp filtering.\n
-                            - Level 0: No filtering.\n
-                            - Level 1: Seccomp filtering by syscall number.\n
-                            - Level 2: Seccomp filtering by syscall number and argument values.\n",
-    ));
-    fc_args.push(ArgumentInfo::new(
-        "start-time-us",
-        false,
-        None,
-        None,
-        true,
-        None,
-        "",
-    ));
-    fc_args.push(ArgumentInfo::new(
-        "start-time-cpu-us",
-        false,
-        None,
-        None,
-        true,
-        None,
-        "",
-    ));
-    fc_args.push(ArgumentInfo::new(
-        "no-api",
-        false,
-        None,
-        Some("config-file"),
-        false,
-        None,
-        "Optional parameter which allows starting and using a microVM without an active API socket.",
-    ));
-    fc_args.push(ArgumentInfo::new(
-        "config-file",
-        false,
-        None,
-        None,
-        true,
-        None,
-        "Path to a file that contains the microVM configuration in JSON format.",
-    ));
-    fc_args.push(ArgumentInfo::new(
-        "extra-args",
-        false,
-        None,
-        None,
-        true,
-        None,
-        "Arguments that will be passed verbatim to the exec file.",
-    ));
-
-    if params.contains(&"help") {
-        println!("Stuff about firecracker");
-        for arg in fc_args {
-            arg.display_help();
+/// Normalizes `--name=value`, `-x` and clustered boolean short flags (`-nh`) into the
+/// plain `--name value` shape the rest of the parser expects. Tokens once past a `--`
+/// separator are copied through untouched, since they're opaque passthrough arguments.
+///
+/// A token sitting in value position (right after a `takes_value` flag) is copied
+/// through verbatim instead of being run through flag normalization, so values like
+/// `-5` or `-foo` aren't mistaken for short flags of their own.
+fn normalize_tokens<'a>(command: &Command<'a>, args: &[&'a str]) -> Result<Vec<String>, Error> {
+    let mut normalized = Vec::new();
+    let mut iter = args.iter();
+    let mut expecting_value = false;
+    while let Some(&item) = iter.next() {
+        if expecting_value {
+            normalized.push(item.to_string());
+            expecting_value = false;
+        } else if item == "--" {
+            normalized.push(item.to_string());
+            normalized.extend(iter.map(|s| s.to_string()));
+            break;
+        } else if let Some(rest) = item.strip_prefix("--") {
+            match rest.split_once('=') {
+                Some((name, value)) => {
+                    normalized.push(format!("--{}", name));
+                    normalized.push(value.to_string());
+                }
+                None => {
+                    normalized.push(item.to_string());
+                    expecting_value = arg_takes_value(command, rest);
+                }
+            }
+        } else if item.starts_with('-') && item.len() > 1 {
+            let chars: Vec<char> = item[1..].chars().collect();
+            if chars.len() == 1 {
+                let (name, takes_value) = resolve_short(command, chars[0])?;
+                normalized.push(format!("--{}", name));
+                expecting_value = takes_value;
+            } else {
+                for c in chars {
+                    let (name, takes_value) = resolve_short(command, c)?;
+                    if takes_value {
+                        return Err(Error::UnknownArgument(item.to_string()));
+                    }
+                    normalized.push(format!("--{}", name));
+                }
+            }
+        } else {
+            normalized.push(item.to_string());
         }
-        return;
     }
+    Ok(normalized)
+}
+
+/// Validates the already-normalized `args` against `command`'s own argument set. The
+/// first element is expected to be an inert token (the program name or the subcommand
+/// name that was already consumed).
+///
+/// Anything after a bare `--` is passthrough: it's returned verbatim as the second
+/// tuple element rather than being validated against `command`, since those tokens are
+/// meant for whatever the caller execs next, not for this parser (mirrors how
+/// Firecracker is invoked as `--api-sock /x -- <exec flags>`).
+fn validate_command(
+    command: &Command<'_>,
+    args: &[String],
+) -> Result<(ParsedValues, Vec<String>), Error> {
+    let separator = args.iter().position(|x| x == "--");
+    let (command_args, extra_args) = match separator {
+        Some(index) => (&args[..index], args[index + 1..].to_vec()),
+        None => (args, Vec::new()),
+    };
+
+    let params: Vec<&str> = command_args
+        .iter()
+        .filter(|x| x.starts_with("--"))
+        .map(|x| x.trim_start_matches("--"))
+        .collect();
+
+    let fc_args_str: Vec<&str> = command.args().iter().map(|a| a.name).collect();
+    let mut values: ParsedValues = HashMap::new();
 
-    for (i, &item) in args.iter().enumerate() {
-        if item.starts_with("--") && item != "--" {
-            for argument_info in fc_args.iter() {
+    for (i, item) in command_args.iter().enumerate() {
+        if item.starts_with("--") {
+            for argument_info in command.args() {
                 let name = item.trim_start_matches("--");
                 if !fc_args_str.contains(&name) {
-                    panic!("Found argument '{}' which wasn't expected, or isn't valid in this context.", name);
+                    return Err(Error::UnknownArgument(name.to_string()));
                 }
                 if argument_info.name == name {
-                    check_is_valid(argument_info, &params);
+                    check_is_valid(argument_info, &params)?;
                     if argument_info.takes_value {
-                        if args.get(i + 1).is_some() {
-                            if let Some(&x) = args.get(i + 2) {
+                        if command_args.get(i + 1).is_some() {
+                            if let Some(x) = command_args.get(i + 2) {
                                 if !x.starts_with("--") {
-                                    panic!("Found argument '{}' which wasn't expected, or isn't valid in this context.", x);
+                                    return Err(Error::UnknownArgument(x.to_string()));
                                 }
                             }
                         }
-                        let param_value = args.get(i + 1).map(|x| *x).unwrap();
-                        if name == "seccomp-level" && !seccomp_values.contains(&param_value) {
-                            panic!(
-                                "'{}' isn't a valid value for 'seccomp-level'. Must  be 0, 1 or 2.",
-                                param_value
-                            );
+                        let param_value = command_args
+                            .get(i + 1)
+                            .ok_or_else(|| Error::MissingRequiredValue(name.to_string()))?;
+                        if let Some(value_parser) = &argument_info.value_parser {
+                            value_parser.validate(name, param_value)?;
+                        }
+                        let occurrences = values.entry(name.to_string()).or_default();
+                        if !occurrences.is_empty() && !argument_info.arity.is_repeated() {
+                            return Err(Error::DuplicateArgument(name.to_string()));
                         }
-                        values.insert(name, param_value);
-                        println!("{} {:?}", item, values.get(name));
+                        occurrences.push(param_value.clone());
                     } else {
-                        if let Some(&x) = args.get(i + 1) {
+                        if let Some(x) = command_args.get(i + 1) {
                             if !x.starts_with("--") {
-                                panic!("Found argument '{}' which wasn't expected, or isn't valid in this context.", x);
+                                return Err(Error::UnexpectedValue(name.to_string(), x.to_string()));
                             }
                         }
-                        values.insert(name, "");
-                        println!("{} {:?}", item, values.get(name));
+                        let occurrences = values.entry(name.to_string()).or_default();
+                        if !occurrences.is_empty() && !argument_info.arity.is_repeated() {
+                            return Err(Error::DuplicateArgument(name.to_string()));
+                        }
+                        occurrences.push(String::new());
                     }
                 }
             }
         }
     }
-    for arg in fc_args.iter() {
-        if arg.required && !params.contains(&arg.name) {
-            values.insert(arg.name, arg.default_value.unwrap());
+    Ok((values, extra_args))
+}
+
+/// Merges the argument values parsed from the command line with `command`'s declared
+/// defaults and, if `--config-file` was used, the values found there. Precedence is
+/// CLI arguments, then the config file, then defaults, matching how virtiofsd/Firecracker
+/// let operators supply configuration via file or flags interchangeably.
+fn merge_values(
+    command: &Command<'_>,
+    cli_values: ParsedValues,
+) -> Result<ParsedValues, Error> {
+    let mut values: ParsedValues = HashMap::new();
+
+    for arg in command.args() {
+        if let Some(default) = arg.default_value {
+            values.insert(arg.name.to_string(), vec![default.to_string()]);
+        }
+    }
+
+    if let Some(path) = cli_values.get("config-file").and_then(|v| v.first()) {
+        for (name, value) in config::load(path)? {
+            let argument_info = command
+                .args()
+                .iter()
+                .find(|arg| arg.name == name)
+                .ok_or_else(|| Error::UnknownArgument(name.clone()))?;
+            if let Some(value_parser) = &argument_info.value_parser {
+                value_parser.validate(&name, &value)?;
+            }
+            values.insert(name, vec![value]);
         }
     }
 
+    for (name, occurrences) in cli_values {
+        values.insert(name, occurrences);
+    }
+
+    Ok(values)
+}
+
+/// Builds the tree of commands understood by this binary.
+///
+/// There are currently no nested subcommands declared, so every invocation resolves to
+/// the [`ANONYMOUS_COMMAND_NAME`] top-level command; the dispatch machinery is in place
+/// for tools that do want a `run`/`snapshot`/`restore`-style split.
+fn build_command<'a>() -> Command<'a> {
+    Command::new(ANONYMOUS_COMMAND_NAME)
+        .arg(
+            ArgumentInfo::named("api-sock")
+                .required(false)
+                .takes_value()
+                .default(DEFAULT_API_SOCK_PATH)
+                .help("Path to unix domain socket used by the API")
+                .value_parser(ValueParser::Path)
+                .heading("API Options"),
+        )
+        .arg(
+            ArgumentInfo::named("id")
+                .required(false)
+                .takes_value()
+                .default(DEFAULT_INSTANCE_ID)
+                .help("MicroVM unique identifier")
+                .value_parser(ValueParser::String)
+                .short('i')
+                .heading("Instance Options"),
+        )
+        .arg(
+            ArgumentInfo::named("seccomp-level")
+                .required(false)
+                .takes_value()
+                .default("2")
+                .help(
+                    "Level of seccomp filtering.\n
+                            - Level 0: No filtering.\n
+                            - Level 1: Seccomp filtering by syscall number.\n
+                            - Level 2: Seccomp filtering by syscall number and argument values.\n",
+                )
+                .value_parser(ValueParser::PossibleValues(vec!["0", "1", "2"]))
+                .heading("Instance Options"),
+        )
+        .arg(
+            ArgumentInfo::named("start-time-us")
+                .required(false)
+                .takes_value()
+                .value_parser(ValueParser::I64)
+                .heading("Instance Options"),
+        )
+        .arg(
+            ArgumentInfo::named("start-time-cpu-us")
+                .required(false)
+                .takes_value()
+                .value_parser(ValueParser::I64)
+                .heading("Instance Options"),
+        )
+        .arg(
+            ArgumentInfo::named("no-api")
+                .required(false)
+                .requires("config-file")
+                .help(
+                    "Optional parameter which allows starting and using a microVM without an \
+                     active API socket.",
+                )
+                .short('n')
+                .heading("API Options"),
+        )
+        .arg(
+            ArgumentInfo::named("config-file")
+                .required(false)
+                .takes_value()
+                .help("Path to a file that contains the microVM configuration in JSON format.")
+                .value_parser(ValueParser::Path)
+                .heading("Configuration Options"),
+        )
+        .arg(
+            ArgumentInfo::named("extra-args")
+                .required(false)
+                .takes_value()
+                .help("Arguments that will be passed verbatim to the exec file.")
+                .value_parser(ValueParser::String)
+                .heading("Passthrough Arguments"),
+        )
+        .arg(
+            ArgumentInfo::named("drive")
+                .repeated()
+                .takes_value()
+                .help("Path to a drive image; may be specified multiple times to attach several drives.")
+                .value_parser(ValueParser::Path)
+                .heading("Configuration Options"),
+        )
+}
+
+/// Picks which `Command` in `root`'s tree should handle `args`: if the first token after
+/// the program name names one of `root`'s subcommands, validation runs against that
+/// subcommand's own flag set; otherwise it falls back to `root` itself. Returns the
+/// resolved command, the path of command names that were resolved (for
+/// [`Arguments::command_path`]), and the slice of `args` left for
+/// `normalize_tokens`/`validate_command` to consume (still headed by an inert token: the
+/// program name, or the subcommand name that was just consumed).
+fn resolve_command<'a, 'b>(root: &'a Command<'a>, args: &'b [&'a str]) -> (&'a Command<'a>, Vec<String>, &'b [&'a str]) {
+    let subcommand = args
+        .get(1)
+        .filter(|tok| !tok.starts_with("--"))
+        .and_then(|tok| root.find_subcommand(tok));
+
+    match subcommand {
+        Some(command) => (command, vec![root.name().to_string(), command.name().to_string()], &args[1..]),
+        None => (root, vec![root.name().to_string()], args),
+    }
+}
+
+/// Parses `args` (including the program name at index 0). The first token after the
+/// program name is checked against the root command's subcommands; if it matches one,
+/// validation runs against that subcommand's flag set, otherwise it falls back to the
+/// anonymous top-level command.
+fn parse(args: &[&str]) -> Result<ParseOutcome, Error> {
+    let root = build_command();
+    let (command, command_path, command_args) = resolve_command(&root, args);
+
+    let normalized = normalize_tokens(command, command_args)?;
+    if wants_help(&normalized) {
+        return Ok(ParseOutcome::Help(help::render_help(command, PROGRAM_NAME, PROGRAM_DESCRIPTION)));
+    }
+
+    let (cli_values, extra_args) = validate_command(command, &normalized)?;
+    let values = merge_values(command, cli_values)?;
+    check_required(command, &values)?;
+
+    Ok(ParseOutcome::Parsed(Arguments { command_path, values, extra_args }))
+}
+
+fn main() {
+    let owned_args: Vec<String> = env::args().collect();
+    let args: Vec<&str> = owned_args.iter().map(|s| s as &str).collect();
+
+    let arguments = match parse(&args) {
+        Ok(ParseOutcome::Parsed(arguments)) => arguments,
+        Ok(ParseOutcome::Help(text)) => {
+            println!("{}", text);
+            process::exit(0);
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+    };
+
     // checking that map values are accessible
-    let _bind_path = values
-        .get("api-sock")
+    let _bind_path = arguments
+        .value_of("api-sock")
         .map(PathBuf::from)
         .expect("Missing argument: api-sock");
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_command<'a>() -> Command<'a> {
+        Command::new(ANONYMOUS_COMMAND_NAME)
+            .arg(ArgumentInfo::named("flag").takes_value().value_parser(ValueParser::String))
+            .arg(ArgumentInfo::named("tag").repeated().takes_value().value_parser(ValueParser::String))
+    }
 
+    fn parse_with<'a>(command: &Command<'a>, args: &[&'a str]) -> Result<ParsedValues, Error> {
+        let normalized = normalize_tokens(command, args)?;
+        validate_command(command, &normalized).map(|(values, _)| values)
+    }
+
+    #[test]
+    fn non_repeated_argument_supplied_twice_is_a_duplicate() {
+        let command = test_command();
+        let args = ["prog", "--flag", "a", "--flag", "b"];
+        assert_eq!(parse_with(&command, &args), Err(Error::DuplicateArgument("flag".to_string())));
+    }
+
+    #[test]
+    fn repeated_argument_accumulates_all_its_values() {
+        let command = test_command();
+        let args = ["prog", "--tag", "a", "--tag", "b"];
+        let values = parse_with(&command, &args).unwrap();
+        assert_eq!(values.get("tag"), Some(&vec!["a".to_string(), "b".to_string()]));
+    }
+
+    #[test]
+    fn help_is_returned_to_the_caller_instead_of_printed_and_exited() {
+        let outcome = parse(&["prog", "--help"]).unwrap();
+        assert!(matches!(outcome, ParseOutcome::Help(ref text) if text.contains("USAGE:")));
+    }
+
+    #[test]
+    fn tokens_after_separator_pass_through_even_if_flag_shaped() {
+        let command = test_command();
+        let args = ["prog", "--flag", "a", "--", "--foo", "bar"];
+        let normalized = normalize_tokens(&command, &args).unwrap();
+        let (values, extra_args) = validate_command(&command, &normalized).unwrap();
+
+        assert_eq!(values.get("flag"), Some(&vec!["a".to_string()]));
+        assert_eq!(extra_args, vec!["--foo".to_string(), "bar".to_string()]);
+    }
+
+    #[test]
+    fn subcommand_tokens_dispatch_against_the_subcommand_flag_set() {
+        let root = Command::new(ANONYMOUS_COMMAND_NAME).subcommand(
+            Command::new("run").arg(ArgumentInfo::named("mem").takes_value().value_parser(ValueParser::I64)),
+        );
+        let args = ["prog", "run", "--mem", "512"];
+
+        let (command, command_path, command_args) = resolve_command(&root, &args);
+        assert_eq!(command.name(), "run");
+        assert_eq!(command_path, vec!["anonymous".to_string(), "run".to_string()]);
+
+        let normalized = normalize_tokens(command, command_args).unwrap();
+        let (values, _) = validate_command(command, &normalized).unwrap();
+        assert_eq!(values.get("mem"), Some(&vec!["512".to_string()]));
+    }
+
+    #[test]
+    fn unmatched_first_token_falls_back_to_the_root_command() {
+        let root = Command::new(ANONYMOUS_COMMAND_NAME)
+            .subcommand(Command::new("run"))
+            .arg(ArgumentInfo::named("id").takes_value().value_parser(ValueParser::String));
+        let args = ["prog", "--id", "foo"];
+
+        let (command, command_path, command_args) = resolve_command(&root, &args);
+        assert_eq!(command.name(), ANONYMOUS_COMMAND_NAME);
+        assert_eq!(command_path, vec!["anonymous".to_string()]);
+        assert_eq!(command_args, &args[..]);
+    }
+
+    /// Writes `contents` to a fresh file under the OS temp dir and returns its path, so
+    /// `config::load` has something real to read.
+    fn write_temp_config(name: &str, contents: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("arg_parser-test-{}-{}", process::id(), name));
+        std::fs::write(&path, contents).unwrap();
+        path
+    }
+
+    fn precedence_command<'a>() -> Command<'a> {
+        Command::new(ANONYMOUS_COMMAND_NAME)
+            .arg(
+                ArgumentInfo::named("id")
+                    .takes_value()
+                    .default("anonymous-instance")
+                    .value_parser(ValueParser::String),
+            )
+            .arg(ArgumentInfo::named("config-file").takes_value().value_parser(ValueParser::Path))
+    }
+
+    #[test]
+    fn config_file_value_overrides_default() {
+        let path = write_temp_config("config-overrides-default", r#"{"id": "from-config"}"#);
+        let command = precedence_command();
+        let mut cli_values = HashMap::new();
+        cli_values.insert("config-file".to_string(), vec![path.to_str().unwrap().to_string()]);
+
+        let values = merge_values(&command, cli_values).unwrap();
+
+        assert_eq!(values.get("id"), Some(&vec!["from-config".to_string()]));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn cli_value_overrides_config_file_and_default() {
+        let path = write_temp_config("cli-overrides-config", r#"{"id": "from-config"}"#);
+        let command = precedence_command();
+        let mut cli_values = HashMap::new();
+        cli_values.insert("config-file".to_string(), vec![path.to_str().unwrap().to_string()]);
+        cli_values.insert("id".to_string(), vec!["from-cli".to_string()]);
+
+        let values = merge_values(&command, cli_values).unwrap();
+
+        assert_eq!(values.get("id"), Some(&vec!["from-cli".to_string()]));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    fn required_command<'a>() -> Command<'a> {
+        Command::new(ANONYMOUS_COMMAND_NAME)
+            .arg(ArgumentInfo::named("id").required(true).takes_value().value_parser(ValueParser::String))
+            .arg(ArgumentInfo::named("config-file").takes_value().value_parser(ValueParser::Path))
+    }
+
+    #[test]
+    fn required_argument_supplied_only_via_config_file_is_satisfied() {
+        let path = write_temp_config("required-via-config", r#"{"id": "from-config"}"#);
+        let command = required_command();
+        let mut cli_values = HashMap::new();
+        cli_values.insert("config-file".to_string(), vec![path.to_str().unwrap().to_string()]);
+
+        let values = merge_values(&command, cli_values).unwrap();
+
+        assert_eq!(check_required(&command, &values), Ok(()));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn required_argument_missing_everywhere_is_rejected() {
+        let command = required_command();
+        let values = merge_values(&command, HashMap::new()).unwrap();
+
+        assert_eq!(
+            check_required(&command, &values),
+            Err(Error::MissingRequiredArgument("id".to_string()))
+        );
+    }
+}
@@ -0,0 +1,98 @@
+use crate::error::Error;
+
+/// Declarative validation for an argument's value, attached to an [`crate::ArgumentInfo`]
+/// so callers don't have to special-case individual flags in the parsing loop.
+///
+/// Mirrors cargo's `value_parser!`/`PossibleValuesParser`: the variants describe *how* a
+/// raw token should be checked, not how it's stored (values are still stored by the
+/// parser as plain strings).
+pub enum ValueParser<'a> {
+    /// Accepts any value.
+    String,
+    /// Accepts any value that parses as an `i64`.
+    I64,
+    /// Accepts any value (reserved for filesystem-path arguments; no extra checks yet).
+    Path,
+    /// Accepts only one of the given values.
+    PossibleValues(Vec<&'a str>),
+    /// Accepts any value for which the given function returns `Ok`.
+    Custom(fn(&str) -> Result<(), Error>),
+}
+
+impl<'a> ValueParser<'a> {
+    /// Validates `value` for the argument `name`, returning [`Error::InvalidValue`] on
+    /// failure.
+    pub fn validate(&self, name: &str, value: &str) -> Result<(), Error> {
+        match self {
+            ValueParser::String | ValueParser::Path => Ok(()),
+            ValueParser::I64 => value
+                .parse::<i64>()
+                .map(|_| ())
+                .map_err(|_| Error::InvalidValue(name.to_string(), value.to_string())),
+            ValueParser::PossibleValues(values) => {
+                if values.contains(&value) {
+                    Ok(())
+                } else {
+                    Err(Error::InvalidValue(name.to_string(), value.to_string()))
+                }
+            }
+            ValueParser::Custom(f) => {
+                f(value).map_err(|_| Error::InvalidValue(name.to_string(), value.to_string()))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn string_and_path_accept_anything() {
+        assert_eq!(ValueParser::String.validate("name", ""), Ok(()));
+        assert_eq!(ValueParser::Path.validate("name", "/not/a/real/path"), Ok(()));
+    }
+
+    #[test]
+    fn i64_accepts_valid_integers_including_negative() {
+        assert_eq!(ValueParser::I64.validate("start-time-us", "-5"), Ok(()));
+        assert_eq!(ValueParser::I64.validate("start-time-us", "1234"), Ok(()));
+    }
+
+    #[test]
+    fn i64_rejects_non_integers() {
+        assert_eq!(
+            ValueParser::I64.validate("start-time-us", "not-a-number"),
+            Err(Error::InvalidValue("start-time-us".to_string(), "not-a-number".to_string()))
+        );
+    }
+
+    #[test]
+    fn possible_values_accepts_listed_value() {
+        let parser = ValueParser::PossibleValues(vec!["0", "1", "2"]);
+        assert_eq!(parser.validate("seccomp-level", "1"), Ok(()));
+    }
+
+    #[test]
+    fn possible_values_rejects_unlisted_value() {
+        let parser = ValueParser::PossibleValues(vec!["0", "1", "2"]);
+        assert_eq!(
+            parser.validate("seccomp-level", "3"),
+            Err(Error::InvalidValue("seccomp-level".to_string(), "3".to_string()))
+        );
+    }
+
+    #[test]
+    fn custom_runs_the_supplied_function() {
+        fn is_even(value: &str) -> Result<(), Error> {
+            match value.parse::<i64>() {
+                Ok(n) if n % 2 == 0 => Ok(()),
+                _ => Err(Error::InvalidValue(String::new(), String::new())),
+            }
+        }
+
+        let parser = ValueParser::Custom(is_even);
+        assert_eq!(parser.validate("count", "4"), Ok(()));
+        assert!(parser.validate("count", "3").is_err());
+    }
+}